@@ -1,12 +1,36 @@
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tauri::{AppHandle, Manager, WebviewWindow, WebviewWindowBuilder};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::http::{Request, Response};
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow, WebviewWindowBuilder};
 use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
 use sysinfo::{System, Disks, Components};
 
+fn default_sandboxed() -> bool {
+    true
+}
+
+// Dependencies are re-fetched once their cache entry is older than this.
+const DEFAULT_DEPENDENCY_MAX_AGE_SECS: u64 = 7 * 24 * 60 * 60;
+
+// Dependency downloads are user-supplied URLs, so they get a request timeout
+// and a response size cap rather than the unbounded fetch a trusted fetch
+// wouldn't need.
+const DEPENDENCY_FETCH_TIMEOUT_SECS: u64 = 30;
+const DEPENDENCY_MAX_RESPONSE_BYTES: usize = 25 * 1024 * 1024;
+
+// Default cadence for the background system-info monitor started in run().
+const DEFAULT_SYSTEM_MONITOR_INTERVAL_MS: u64 = 1000;
+
 // Data structures with serde rename for JavaScript compatibility
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -27,6 +51,18 @@ pub struct Widget {
     pub auto_start: bool,
     #[serde(default)]
     pub locked: bool,
+    #[serde(default = "default_sandboxed")]
+    pub sandboxed: bool,
+    // Cached dependencies to inline into the widget document, in load order.
+    #[serde(default)]
+    pub dependency_ids: Vec<String>,
+    // Pins the widget to a monitor id from `get_monitors`; `x`/`y` are then
+    // relative to that monitor's origin instead of the virtual desktop's.
+    #[serde(default)]
+    pub monitor_id: Option<String>,
+    // Keeps the widget window visible across every virtual desktop / Space.
+    #[serde(default)]
+    pub visible_on_all_workspaces: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,6 +85,19 @@ pub struct Dependency {
     pub added_at: String,
 }
 
+// Sidecar metadata for a cached dependency, stored in the cache manifest
+// rather than on `Dependency` itself so re-fetch bookkeeping stays separate
+// from the user-facing dependency list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyCacheEntry {
+    pub url_hash: String,
+    pub content_hash: String,
+    pub fetched_at: u64,
+    pub size_bytes: u64,
+    pub ext: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SystemInfo {
@@ -62,9 +111,43 @@ pub struct SystemInfo {
     pub cpu_temperature: Option<f32>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorInfo {
+    pub id: String,
+    pub name: Option<String>,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f64,
+    pub primary: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IsolationPayload {
+    pub nonce: String,
+    pub payload: String,
+}
+
 pub struct AppState {
     pub widget_windows: Mutex<HashMap<String, WebviewWindow>>,
+    // Rendered widget documents served by the widget:// protocol, keyed by
+    // widget id, so widget source never touches disk.
+    pub widget_documents: Mutex<HashMap<String, String>>,
+    // Per-widget dependency name -> cached file path, scoping widget://<id>/deps/<name>
+    // to exactly that widget's own declared dependencies.
+    pub widget_dependency_scope: Mutex<HashMap<String, HashMap<String, PathBuf>>>,
     pub system: Arc<Mutex<System>>,
+    // Kept alive across ticks so the background monitor (and the one-shot
+    // fallback command) refresh in place instead of rebuilding these lists.
+    pub disks: Arc<Mutex<Disks>>,
+    pub components: Arc<Mutex<Components>>,
+    pub monitor_task: Mutex<Option<JoinHandle<()>>>,
+    // Per-launch AES-256-GCM key used by the isolation pattern to authenticate
+    // messages the widget frame posts up to the host (see create_widget_window).
+    pub isolation_key: [u8; 32],
 }
 
 // Get config directory
@@ -78,6 +161,60 @@ fn get_cache_dir() -> Result<PathBuf, String> {
     get_config_dir().map(|dir| dir.join("cache"))
 }
 
+fn get_dependency_manifest_path() -> Result<PathBuf, String> {
+    get_cache_dir().map(|dir| dir.join("manifest.json"))
+}
+
+fn load_dependency_manifest() -> Result<HashMap<String, DependencyCacheEntry>, String> {
+    let manifest_file = get_dependency_manifest_path()?;
+
+    if manifest_file.exists() {
+        let content = fs::read_to_string(&manifest_file)
+            .map_err(|e| format!("Failed to read dependency cache manifest: {}", e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse dependency cache manifest: {}", e))
+    } else {
+        Ok(HashMap::new())
+    }
+}
+
+fn save_dependency_manifest(manifest: &HashMap<String, DependencyCacheEntry>) -> Result<(), String> {
+    let manifest_file = get_dependency_manifest_path()?;
+    let content = serde_json::to_string_pretty(manifest)
+        .map_err(|e| format!("Failed to serialize dependency cache manifest: {}", e))?;
+    fs::write(&manifest_file, content)
+        .map_err(|e| format!("Failed to write dependency cache manifest: {}", e))
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    format!("{:x}", hasher.finalize())
+}
+
+fn dependency_cache_file(cache_dir: &Path, url_hash: &str, ext: &str) -> PathBuf {
+    cache_dir.join(format!("{}.{}", url_hash, ext))
+}
+
+fn extension_for_dependency(url: &str, content_type: Option<&str>) -> String {
+    if let Some(ext) = Path::new(url).extension().and_then(|e| e.to_str()) {
+        return ext.to_lowercase();
+    }
+
+    match content_type.unwrap_or("") {
+        ct if ct.contains("css") => "css".to_string(),
+        ct if ct.contains("javascript") => "js".to_string(),
+        _ => "bin".to_string(),
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
 // Ensure directories exist
 fn ensure_directories() -> Result<(), String> {
     let config_dir = get_config_dir()?;
@@ -85,7 +222,106 @@ fn ensure_directories() -> Result<(), String> {
     
     fs::create_dir_all(&config_dir).map_err(|e| format!("Failed to create configuration directory: {}", e))?;
     fs::create_dir_all(&cache_dir).map_err(|e| format!("Failed to create cache directory: {}", e))?;
-    
+
+    Ok(())
+}
+
+// Isolation pattern: encrypts/authenticates messages the untrusted widget frame
+// posts up to the host, so a payload without a valid AES-GCM tag is rejected
+// instead of being trusted at face value.
+fn encrypt_isolation_payload(key: &[u8; 32], plaintext: &str) -> Result<IsolationPayload, String> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+
+    let mut nonce_bytes = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut nonce_bytes);
+    let nonce = Nonce::from_slice(&nonce_bytes);
+
+    let ciphertext = cipher
+        .encrypt(nonce, plaintext.as_bytes())
+        .map_err(|e| format!("Failed to encrypt isolation payload: {}", e))?;
+
+    Ok(IsolationPayload {
+        nonce: STANDARD.encode(nonce_bytes),
+        payload: STANDARD.encode(ciphertext),
+    })
+}
+
+fn decrypt_isolation_payload(key: &[u8; 32], payload: &IsolationPayload) -> Result<String, String> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+
+    let nonce_bytes = STANDARD
+        .decode(&payload.nonce)
+        .map_err(|e| format!("Invalid isolation nonce: {}", e))?;
+    let ciphertext = STANDARD
+        .decode(&payload.payload)
+        .map_err(|e| format!("Invalid isolation payload: {}", e))?;
+
+    // `Nonce::from_slice` asserts on length rather than returning a `Result`,
+    // so a short/long nonce has to be rejected here or it panics instead of
+    // falling into the auth-failure error arm below like every other
+    // malformed payload.
+    if nonce_bytes.len() != 12 {
+        return Err("Failed to authenticate isolation payload".to_string());
+    }
+
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(&nonce_bytes), ciphertext.as_ref())
+        .map_err(|_| "Failed to authenticate isolation payload".to_string())?;
+
+    String::from_utf8(plaintext).map_err(|e| format!("Isolation payload was not valid UTF-8: {}", e))
+}
+
+// Called by the trusted isolation frame to encrypt a message before it is
+// accepted by the host. A widget frame can never reach this directly - only
+// the isolation frame holds the bridge that calls it.
+#[tauri::command]
+async fn isolation_encode(state: tauri::State<'_, AppState>, plaintext: String) -> Result<IsolationPayload, String> {
+    encrypt_isolation_payload(&state.isolation_key, &plaintext)
+}
+
+// A widget-originated action, decoded from an isolation payload. Only these
+// variants are reachable from widget JS, regardless of what the untrusted
+// widget frame tries to send - anything else is rejected outright.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "action")]
+enum WidgetAction {
+    Close,
+    Resize { width: u32, height: u32 },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WidgetMessage {
+    widget_id: String,
+    #[serde(flatten)]
+    action: WidgetAction,
+}
+
+// Authenticates a message relayed through the isolation frame and, only once
+// the AES-GCM tag checks out, dispatches it to the small allow-listed set of
+// actions a widget is actually permitted to trigger on its own window. A
+// payload lacking a valid tag - or decoding to anything outside
+// `WidgetAction` - never reaches the window it names.
+#[tauri::command]
+async fn handle_widget_message(state: tauri::State<'_, AppState>, payload: IsolationPayload) -> Result<(), String> {
+    let plaintext = decrypt_isolation_payload(&state.isolation_key, &payload)?;
+    let message: WidgetMessage =
+        serde_json::from_str(&plaintext).map_err(|e| format!("Invalid widget message: {}", e))?;
+
+    let windows = state.widget_windows.lock().await;
+    let window = windows
+        .get(&message.widget_id)
+        .ok_or_else(|| format!("Unknown widget id: {}", message.widget_id))?;
+
+    match message.action {
+        WidgetAction::Close => window
+            .close()
+            .map_err(|e| format!("Failed to close widget window: {}", e))?,
+        WidgetAction::Resize { width, height } => window
+            .set_size(tauri::Size::Logical(tauri::LogicalSize::new(width as f64, height as f64)))
+            .map_err(|e| format!("Failed to resize widget window: {}", e))?,
+    }
+
     Ok(())
 }
 
@@ -167,10 +403,178 @@ async fn delete_widget(widget_id: String, app: AppHandle) -> Result<(), String>
     if let Some(window) = windows.get(&widget_id) {
         let _ = window.close();
     }
-    
+    drop(windows);
+
+    state.widget_documents.lock().await.remove(&widget_id);
+    state.widget_dependency_scope.lock().await.remove(&widget_id);
+
     Ok(())
 }
 
+// Handles widget://<id> (the rendered document) and widget://<id>/deps/<name>
+// (a cached dependency), scoped per widget_dependency_scope so a widget can
+// only reach its own cached dependencies rather than the whole cache dir.
+fn handle_widget_protocol(app: &AppHandle, request: Request<Vec<u8>>) -> Response<Vec<u8>> {
+    fn respond(status: u16, content_type: &str, body: Vec<u8>) -> Response<Vec<u8>> {
+        Response::builder()
+            .status(status)
+            .header("Content-Type", content_type)
+            .body(body)
+            .unwrap_or_else(|_| Response::new(Vec::new()))
+    }
+
+    let uri = request.uri();
+    let widget_id = uri.host().unwrap_or_default();
+    let path = uri.path().trim_start_matches('/');
+    let state = app.state::<AppState>();
+
+    if path.is_empty() {
+        let documents = state.widget_documents.blocking_lock();
+        return match documents.get(widget_id) {
+            Some(html) => respond(200, "text/html; charset=utf-8", html.clone().into_bytes()),
+            None => respond(404, "text/plain", b"widget not found".to_vec()),
+        };
+    }
+
+    if let Some(name) = path.strip_prefix("deps/") {
+        let scope = state.widget_dependency_scope.blocking_lock();
+        let cache_file = scope.get(widget_id).and_then(|deps| deps.get(name));
+        return match cache_file.and_then(|path| fs::read(path).ok()) {
+            Some(bytes) => respond(200, "application/octet-stream", bytes),
+            None => respond(404, "text/plain", b"dependency not found".to_vec()),
+        };
+    }
+
+    respond(404, "text/plain", b"not found".to_vec())
+}
+
+// Builds the trusted isolation frame, modeled on Tauri's isolation pattern:
+// the untrusted widget document is rendered inside a sandboxed inner iframe
+// that can only reach the host by posting a message to this frame, which
+// seals it with AES-GCM (via isolation_encode) before handing it onward. A
+// message without a valid tag never reaches anything the host trusts.
+fn build_isolation_document(widget_id: &str, widget_document: &str) -> String {
+    let encoded_inner = STANDARD.encode(widget_document.as_bytes());
+    // Host-authored, not taken from the untrusted widget - safe to splice
+    // into the script as a JSON string literal.
+    let widget_id_json = serde_json::to_string(widget_id).unwrap_or_else(|_| "\"\"".to_string());
+
+    format!(
+        r#"<!DOCTYPE html>
+<html style="margin:0;padding:0;height:100%;overflow:hidden;">
+<head><meta charset="UTF-8"></head>
+<body style="margin:0;padding:0;height:100%;overflow:hidden;background:transparent;">
+    <iframe id="widget-frame" sandbox="allow-scripts" style="border:0;width:100%;height:100%;background:transparent;"></iframe>
+    <script>
+        const WIDGET_ID = {widget_id_json};
+        const frame = document.getElementById('widget-frame');
+        frame.srcdoc = atob('{encoded_inner}');
+
+        window.addEventListener('message', async (event) => {{
+            if (event.source !== frame.contentWindow) return;
+            try {{
+                const sealed = await window.__TAURI__.core.invoke('isolation_encode', {{
+                    plaintext: JSON.stringify({{ ...event.data, widgetId: WIDGET_ID }}),
+                }});
+                // The host only acts on this once handle_widget_message has
+                // authenticated the AES-GCM tag - an unsealed or tampered
+                // message never reaches a command the host trusts.
+                await window.__TAURI__.core.invoke('handle_widget_message', {{ payload: sealed }});
+            }} catch (e) {{
+                console.error('Isolation frame rejected widget message:', e);
+            }}
+        }});
+
+        // Downstream relay: the widget frame has no access to __TAURI__ (it's
+        // sandboxed with only allow-scripts), so host-emitted events have to
+        // be forwarded in here via postMessage rather than listened for directly.
+        window.__TAURI__.event.listen('system-info', (event) => {{
+            frame.contentWindow.postMessage({{ type: 'system-info', payload: event.payload }}, '*');
+        }});
+    </script>
+</body>
+</html>"#,
+        widget_id_json = widget_id_json,
+        encoded_inner = encoded_inner
+    )
+}
+
+// Resolves a widget's dependency_ids (in order) against the saved
+// dependency list and cache manifest, returning ready-to-inline
+// `<style>`/`<script>` blocks for whichever of them are actually cached.
+// Dependencies that were never fetched (or fell out of the cache) are
+// silently skipped rather than failing widget creation.
+fn build_dependency_markup(dependency_ids: &[String]) -> Result<(String, String), String> {
+    if dependency_ids.is_empty() {
+        return Ok((String::new(), String::new()));
+    }
+
+    let config_dir = get_config_dir()?;
+    let dependencies_file = config_dir.join("dependencies.json");
+    let dependencies: Vec<Dependency> = if dependencies_file.exists() {
+        let content = fs::read_to_string(&dependencies_file)
+            .map_err(|e| format!("Failed to read dependencies file: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse dependencies: {}", e))?
+    } else {
+        vec![]
+    };
+
+    let manifest = load_dependency_manifest()?;
+    let cache_dir = get_cache_dir()?;
+
+    let mut styles = String::new();
+    let mut scripts = String::new();
+
+    for id in dependency_ids {
+        if dependencies.iter().find(|d| &d.id == id).is_none() {
+            continue;
+        }
+        let Some(entry) = manifest.get(id) else { continue };
+        let cache_file = dependency_cache_file(&cache_dir, &entry.url_hash, &entry.ext);
+        let Ok(contents) = fs::read_to_string(&cache_file) else { continue };
+
+        match entry.ext.as_str() {
+            "css" => styles.push_str(&format!("<style>{}</style>\n", contents)),
+            "js" => scripts.push_str(&format!("<script>{}</script>\n", contents)),
+            _ => {}
+        }
+    }
+
+    Ok((styles, scripts))
+}
+
+// Resolves a widget's dependency_ids to a name -> cached file path map, used
+// to scope the widget:// protocol's `/deps/<name>` route to exactly this
+// widget's own cached dependencies (and nothing else in the cache dir).
+fn build_dependency_scope(dependency_ids: &[String]) -> Result<HashMap<String, PathBuf>, String> {
+    if dependency_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let config_dir = get_config_dir()?;
+    let dependencies_file = config_dir.join("dependencies.json");
+    let dependencies: Vec<Dependency> = if dependencies_file.exists() {
+        let content = fs::read_to_string(&dependencies_file)
+            .map_err(|e| format!("Failed to read dependencies file: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse dependencies: {}", e))?
+    } else {
+        vec![]
+    };
+
+    let manifest = load_dependency_manifest()?;
+    let cache_dir = get_cache_dir()?;
+
+    let mut scope = HashMap::new();
+    for id in dependency_ids {
+        let Some(dependency) = dependencies.iter().find(|d| &d.id == id) else { continue };
+        let Some(entry) = manifest.get(id) else { continue };
+        let cache_file = dependency_cache_file(&cache_dir, &entry.url_hash, &entry.ext);
+        scope.insert(dependency.name.clone(), cache_file);
+    }
+
+    Ok(scope)
+}
+
 #[tauri::command]
 async fn create_widget_window(widget: Widget, app: AppHandle) -> Result<String, String> {
     let state = app.state::<AppState>();
@@ -181,6 +585,9 @@ async fn create_widget_window(widget: Widget, app: AppHandle) -> Result<String,
         let _ = existing.close();
     }
     
+    // Resolve cached dependencies so widgets can rely on them offline.
+    let (dependency_styles, dependency_scripts) = build_dependency_markup(&widget.dependency_ids)?;
+
     // Create widget HTML content - clean, no controls
     let widget_html = format!(
         r#"<!DOCTYPE html>
@@ -195,7 +602,7 @@ async fn create_widget_window(widget: Widget, app: AppHandle) -> Result<String,
             padding: 0;
             box-sizing: border-box;
         }}
-        
+
         html, body {{
             width: 100%;
             height: 100%;
@@ -203,18 +610,20 @@ async fn create_widget_window(widget: Widget, app: AppHandle) -> Result<String,
             background: transparent;
             font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif;
         }}
-        
+
         #widget-root {{
             width: 100%;
             height: 100%;
             opacity: {opacity};
         }}
-        
+
         {css}
     </style>
+    {dependency_styles}
 </head>
 <body>
     <div id="widget-root">{html}</div>
+    {dependency_scripts}
     <script>
         try {{ {js} }} catch(e) {{ console.error('Widget error:', e); }}
     </script>
@@ -223,22 +632,39 @@ async fn create_widget_window(widget: Widget, app: AppHandle) -> Result<String,
         title = widget.name,
         opacity = widget.opacity as f32 / 100.0,
         css = widget.css,
+        dependency_styles = dependency_styles,
         html = widget.html,
+        dependency_scripts = dependency_scripts,
         js = widget.js
     );
-    
-    // Write HTML file to config directory (not monitored by dev server)
-    let widgets_dir = get_config_dir()?.join("widgets");
-    
-    fs::create_dir_all(&widgets_dir).map_err(|e| format!("Failed to create widgets directory: {}", e))?;
-    
-    let widget_file = widgets_dir.join(format!("{}.html", widget.id));
-    fs::write(&widget_file, &widget_html)
-        .map_err(|e| format!("Failed to write widget HTML: {}", e))?;
-    
-    // Use file:// URL to load from config directory
-    let widget_url = format!("file://{}", widget_file.display());
-    
+
+    // Sandboxed widgets (the default) never load as the top-level document.
+    // Instead the trusted isolation frame loads first and renders the widget
+    // markup inside a sandboxed inner iframe, so widget JS only ever talks to
+    // the host through authenticated, AES-GCM-sealed messages.
+    let document = if widget.sandboxed {
+        build_isolation_document(&widget.id, &widget_html)
+    } else {
+        widget_html
+    };
+
+    // Serve the rendered document and its scoped dependencies through the
+    // widget:// protocol instead of writing widget source to disk - this
+    // also keeps relative asset URLs inside the widget resolvable.
+    let dependency_scope = build_dependency_scope(&widget.dependency_ids)?;
+    state.widget_documents.lock().await.insert(widget.id.clone(), document);
+    state
+        .widget_dependency_scope
+        .lock()
+        .await
+        .insert(widget.id.clone(), dependency_scope);
+
+    let widget_url = format!("widget://{}", widget.id);
+
+    // Translate the widget's monitor-relative position into global
+    // coordinates so pinned widgets land on the right display.
+    let (origin_x, origin_y) = resolve_monitor_origin(&app, &widget.monitor_id)?;
+
     // Create new window with correct Tauri v2 API
     let window = WebviewWindowBuilder::new(
         &app,
@@ -252,7 +678,8 @@ async fn create_widget_window(widget: Widget, app: AppHandle) -> Result<String,
     .transparent(widget.transparent)
     .always_on_top(widget.always_on_top)
     .skip_taskbar(true)
-    .position(widget.x as f64, widget.y as f64)
+    .visible_on_all_workspaces(widget.visible_on_all_workspaces)
+    .position((widget.x + origin_x) as f64, (widget.y + origin_y) as f64)
     .build()
     .map_err(|e| format!("Failed to create widget window: {}", e))?;
     
@@ -265,11 +692,14 @@ async fn create_widget_window(widget: Widget, app: AppHandle) -> Result<String,
 async fn close_widget_window(widget_id: String, app: AppHandle) -> Result<(), String> {
     let state = app.state::<AppState>();
     let mut windows = state.widget_windows.lock().await;
-    
+
     if let Some(window) = windows.remove(&widget_id) {
         window.close().map_err(|e| format!("Failed to close widget window: {}", e))?;
     }
-    
+
+    state.widget_documents.lock().await.remove(&widget_id);
+    state.widget_dependency_scope.lock().await.remove(&widget_id);
+
     Ok(())
 }
 
@@ -443,26 +873,219 @@ async fn remove_dependency(dependency_id: String) -> Result<(), String> {
         .map_err(|e| format!("Failed to serialize dependencies: {}", e))?;
     fs::write(&dependencies_file, content)
         .map_err(|e| format!("Failed to write dependencies file: {}", e))?;
-    
+
     Ok(())
 }
 
-// Autostart command - creates/removes .desktop file in autostart directory
+// Downloads a dependency into the cache (skipping the download if an entry
+// already exists and is within max_age_secs), and flips `cached` to true on
+// the saved dependency entry.
 #[tauri::command]
-async fn set_autostart(enabled: bool) -> Result<(), String> {
+async fn fetch_dependency(dependency: Dependency, max_age_secs: Option<u64>) -> Result<Dependency, String> {
+    ensure_directories()?;
+
+    let cache_dir = get_cache_dir()?;
+    let max_age = max_age_secs.unwrap_or(DEFAULT_DEPENDENCY_MAX_AGE_SECS);
+    let mut manifest = load_dependency_manifest()?;
+
+    // A manifest entry is only trustworthy for the dependency's *current*
+    // URL - if the URL was edited, the cache still holds the old URL's
+    // content under the old url_hash and must be treated as a miss.
+    let current_url_hash = sha256_hex(dependency.url.as_bytes());
+    let fresh = manifest
+        .get(&dependency.id)
+        .map(|entry| {
+            entry.url_hash == current_url_hash
+                && unix_now().saturating_sub(entry.fetched_at) < max_age
+                && dependency_cache_file(&cache_dir, &entry.url_hash, &entry.ext).exists()
+        })
+        .unwrap_or(false);
+
+    if !fresh {
+        // Dependency URLs are user-supplied, so reject anything that isn't
+        // https before ever making a request. Only the scheme is checked: an
+        // https URL whose host is an internal or link-local address is still
+        // fetched.
+        if !dependency.url.starts_with("https://") {
+            return Err("Dependency URL must use https".to_string());
+        }
+
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(DEPENDENCY_FETCH_TIMEOUT_SECS))
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+        let mut response = client
+            .get(&dependency.url)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to download dependency: {}", e))?;
+
+        if response
+            .content_length()
+            .is_some_and(|len| len > DEPENDENCY_MAX_RESPONSE_BYTES as u64)
+        {
+            return Err(format!(
+                "Dependency response exceeds the {} byte limit",
+                DEPENDENCY_MAX_RESPONSE_BYTES
+            ));
+        }
+
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string());
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| format!("Failed to read dependency response: {}", e))?
+        {
+            if bytes.len() + chunk.len() > DEPENDENCY_MAX_RESPONSE_BYTES {
+                return Err(format!(
+                    "Dependency response exceeds the {} byte limit",
+                    DEPENDENCY_MAX_RESPONSE_BYTES
+                ));
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+
+        let ext = extension_for_dependency(&dependency.url, content_type.as_deref());
+        let cache_file = dependency_cache_file(&cache_dir, &current_url_hash, &ext);
+
+        fs::write(&cache_file, &bytes).map_err(|e| format!("Failed to write cached dependency: {}", e))?;
+
+        manifest.insert(
+            dependency.id.clone(),
+            DependencyCacheEntry {
+                url_hash: current_url_hash,
+                content_hash: sha256_hex(&bytes),
+                fetched_at: unix_now(),
+                size_bytes: bytes.len() as u64,
+                ext,
+            },
+        );
+        save_dependency_manifest(&manifest)?;
+    }
+
+    let mut cached_dependency = dependency;
+    cached_dependency.cached = true;
+    add_dependency(cached_dependency.clone()).await?;
+
+    Ok(cached_dependency)
+}
+
+// Deletes every cached dependency file and manifest entry, and marks all
+// saved dependencies as no longer cached so the UI reflects the purge.
+#[tauri::command]
+async fn purge_cache() -> Result<(), String> {
+    ensure_directories()?;
+
+    let cache_dir = get_cache_dir()?;
+    for entry in fs::read_dir(&cache_dir).map_err(|e| format!("Failed to read cache directory: {}", e))? {
+        let entry = entry.map_err(|e| format!("Failed to read cache entry: {}", e))?;
+        if entry.path().is_file() {
+            fs::remove_file(entry.path()).map_err(|e| format!("Failed to remove cached file: {}", e))?;
+        }
+    }
+
+    save_dependency_manifest(&HashMap::new())?;
+
+    let config_dir = get_config_dir()?;
+    let dependencies_file = config_dir.join("dependencies.json");
+    let mut dependencies = get_dependencies().await?;
+    for dependency in dependencies.iter_mut() {
+        dependency.cached = false;
+    }
+
+    let content = serde_json::to_string_pretty(&dependencies)
+        .map_err(|e| format!("Failed to serialize dependencies: {}", e))?;
+    fs::write(&dependencies_file, content)
+        .map_err(|e| format!("Failed to write dependencies file: {}", e))?;
+
+    Ok(())
+}
+
+// Packaging formats relaunch the app from somewhere other than a stable
+// install path, so `current_exe()` alone doesn't give autostart a command
+// that will still resolve next boot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PackageFormat {
+    AppImage,
+    Flatpak,
+    Snap,
+    Native,
+}
+
+fn detect_package_format() -> PackageFormat {
+    detect_package_format_from(
+        std::env::var_os("APPIMAGE").is_some(),
+        Path::new("/.flatpak-info").exists(),
+        std::env::var_os("SNAP").is_some(),
+    )
+}
+
+// Split out of detect_package_format so the precedence between markers can
+// be exercised without touching real env vars or the filesystem.
+fn detect_package_format_from(has_appimage: bool, has_flatpak_info: bool, has_snap: bool) -> PackageFormat {
+    if has_appimage {
+        PackageFormat::AppImage
+    } else if has_flatpak_info {
+        PackageFormat::Flatpak
+    } else if has_snap {
+        PackageFormat::Snap
+    } else {
+        PackageFormat::Native
+    }
+}
+
+// Resolves the command that should actually be launched on login. AppImage
+// mounts the binary under a temp FUSE path that disappears after exit,
+// Flatpak and Snap run everything through a confined launcher, so each gets
+// its own stable entry point instead of `current_exe()`.
+fn resolve_launch_command() -> Result<String, String> {
+    resolve_launch_command_for(detect_package_format())
+}
+
+// Split out of resolve_launch_command so each format's resolution can be
+// tested by passing the format directly, instead of having to fake the env
+// vars and filesystem state that would make detect_package_format() return it.
+fn resolve_launch_command_for(format: PackageFormat) -> Result<String, String> {
+    match format {
+        PackageFormat::AppImage => {
+            std::env::var("APPIMAGE").map_err(|e| format!("Failed to read APPIMAGE: {}", e))
+        }
+        PackageFormat::Flatpak => {
+            let app_id = std::env::var("FLATPAK_ID")
+                .map_err(|e| format!("Failed to read FLATPAK_ID: {}", e))?;
+            Ok(format!("flatpak run {}", app_id))
+        }
+        PackageFormat::Snap => {
+            let snap_name = std::env::var("SNAP_NAME").unwrap_or_else(|_| "dashlayer".to_string());
+            Ok(format!("snap run {}", snap_name))
+        }
+        PackageFormat::Native => {
+            let exe_path = std::env::current_exe()
+                .map_err(|e| format!("Failed to get executable path: {}", e))?;
+            Ok(exe_path.display().to_string())
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_autostart_platform(enabled: bool, launch_command: &str) -> Result<(), String> {
     let autostart_dir = dirs::config_dir()
         .map(|d| d.join("autostart"))
-        .ok_or("Failed to get autostart directory")?;
-    
+        .ok_or_else(|| "Failed to get autostart directory".to_string())?;
+
     fs::create_dir_all(&autostart_dir)
         .map_err(|e| format!("Failed to create autostart directory: {}", e))?;
-    
+
     let desktop_file = autostart_dir.join("dashlayer.desktop");
-    
+
     if enabled {
-        let exe_path = std::env::current_exe()
-            .map_err(|e| format!("Failed to get executable path: {}", e))?;
-        
         let content = format!(
             r#"[Desktop Entry]
 Type=Application
@@ -475,36 +1098,192 @@ Categories=Utility;
 StartupNotify=false
 X-GNOME-Autostart-enabled=true
 "#,
-            exe_path.display()
+            launch_command
         );
-        
+
         fs::write(&desktop_file, content)
             .map_err(|e| format!("Failed to write autostart file: {}", e))?;
-    } else {
-        if desktop_file.exists() {
-            fs::remove_file(&desktop_file)
-                .map_err(|e| format!("Failed to remove autostart file: {}", e))?;
+    } else if desktop_file.exists() {
+        fs::remove_file(&desktop_file)
+            .map_err(|e| format!("Failed to remove autostart file: {}", e))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn get_autostart_platform() -> Result<bool, String> {
+    let autostart_dir = dirs::config_dir()
+        .map(|d| d.join("autostart"))
+        .ok_or_else(|| "Failed to get autostart directory".to_string())?;
+
+    Ok(autostart_dir.join("dashlayer.desktop").exists())
+}
+
+#[cfg(target_os = "macos")]
+fn launch_agent_path() -> Result<PathBuf, String> {
+    dirs::home_dir()
+        .map(|home| home.join("Library/LaunchAgents/com.dashlayer.app.plist"))
+        .ok_or_else(|| "Failed to get home directory".to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn set_autostart_platform(enabled: bool, launch_command: &str) -> Result<(), String> {
+    let plist_path = launch_agent_path()?;
+
+    if enabled {
+        if let Some(parent) = plist_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create LaunchAgents directory: {}", e))?;
         }
+
+        let content = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>com.dashlayer.app</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#,
+            launch_command
+        );
+
+        fs::write(&plist_path, content)
+            .map_err(|e| format!("Failed to write LaunchAgent plist: {}", e))?;
+    } else if plist_path.exists() {
+        fs::remove_file(&plist_path)
+            .map_err(|e| format!("Failed to remove LaunchAgent plist: {}", e))?;
     }
-    
+
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn get_autostart_platform() -> Result<bool, String> {
+    Ok(launch_agent_path()?.exists())
+}
+
+#[cfg(target_os = "windows")]
+fn set_autostart_platform(enabled: bool, launch_command: &str) -> Result<(), String> {
+    let hkcu = winreg::RegKey::predef(winreg::enums::HKEY_CURRENT_USER);
+    let (run_key, _) = hkcu
+        .create_subkey(r"Software\Microsoft\Windows\CurrentVersion\Run")
+        .map_err(|e| format!("Failed to open autostart registry key: {}", e))?;
+
+    if enabled {
+        run_key
+            .set_value("DashLayer", &launch_command)
+            .map_err(|e| format!("Failed to write autostart registry value: {}", e))?;
+    } else {
+        let _ = run_key.delete_value("DashLayer");
+    }
+
     Ok(())
 }
 
+#[cfg(target_os = "windows")]
+fn get_autostart_platform() -> Result<bool, String> {
+    let hkcu = winreg::RegKey::predef(winreg::enums::HKEY_CURRENT_USER);
+    let run_key = hkcu
+        .open_subkey(r"Software\Microsoft\Windows\CurrentVersion\Run")
+        .map_err(|e| format!("Failed to open autostart registry key: {}", e))?;
+
+    Ok(run_key.get_value::<String, _>("DashLayer").is_ok())
+}
+
+// Autostart commands - dispatch to the platform-specific mechanism and
+// substitute a packaging-stable launch command where current_exe() alone
+// would point at a temp mount or confined prefix.
+#[tauri::command]
+async fn set_autostart(enabled: bool) -> Result<(), String> {
+    // Disabling never needs a launch command (every platform's disable path
+    // just deletes the autostart entry), so don't let a launch-command
+    // resolution failure block turning autostart off.
+    let launch_command = if enabled { resolve_launch_command()? } else { String::new() };
+    set_autostart_platform(enabled, &launch_command)
+}
+
 #[tauri::command]
 async fn get_autostart() -> Result<bool, String> {
-    let autostart_dir = dirs::config_dir()
-        .map(|d| d.join("autostart"))
-        .ok_or("Failed to get autostart directory")?;
-    
-    let desktop_file = autostart_dir.join("dashlayer.desktop");
-    Ok(desktop_file.exists())
+    get_autostart_platform()
 }
 
-// Get screen info for visual positioning
+// Monitor enumeration, built on Tauri's runtime monitor access, so widget
+// positioning can account for mixed-DPI multi-monitor setups instead of
+// guessing against a single hardcoded resolution.
 #[tauri::command]
-async fn get_screen_size() -> Result<(u32, u32), String> {
-    // Return a default screen size - in real app would query the system
-    Ok((1920, 1080))
+async fn get_monitors(app: AppHandle) -> Result<Vec<MonitorInfo>, String> {
+    let monitors = app
+        .available_monitors()
+        .map_err(|e| format!("Failed to enumerate monitors: {}", e))?;
+    let primary_position = app
+        .primary_monitor()
+        .map_err(|e| format!("Failed to get primary monitor: {}", e))?
+        .map(|m| *m.position());
+
+    Ok(monitors
+        .iter()
+        .map(|monitor| {
+            let name = monitor.name().cloned();
+            MonitorInfo {
+                id: monitor_id(monitor),
+                name,
+                x: monitor.position().x,
+                y: monitor.position().y,
+                width: monitor.size().width,
+                height: monitor.size().height,
+                scale_factor: monitor.scale_factor(),
+                primary: Some(*monitor.position()) == primary_position,
+            }
+        })
+        .collect())
+}
+
+// A monitor's name (when the backend reports one, e.g. "DP-1") is stable
+// across hot-plug/sleep-wake. Falling back to enumeration index is not - it
+// silently relabels a different physical monitor if ordering changes
+// between calls - so the fallback is keyed on geometry instead, which only
+// changes when the monitor's actual resolution or placement does.
+fn monitor_id(monitor: &tauri::Monitor) -> String {
+    monitor.name().cloned().unwrap_or_else(|| {
+        let position = monitor.position();
+        let size = monitor.size();
+        monitor_fallback_id(size.width, size.height, position.x, position.y)
+    })
+}
+
+// Split out of monitor_id so the fallback's format can be exercised without
+// needing a real tauri::Monitor (which has no public constructor).
+fn monitor_fallback_id(width: u32, height: u32, x: i32, y: i32) -> String {
+    format!("{}x{}+{}+{}", width, height, x, y)
+}
+
+// Resolves a widget's pinned monitor id (from get_monitors) to that
+// monitor's global origin, so `widget.x`/`widget.y` can stay relative to the
+// monitor instead of the whole virtual desktop. Unpinned widgets use the
+// virtual desktop's origin, matching today's behavior.
+fn resolve_monitor_origin(app: &AppHandle, monitor_id_to_find: &Option<String>) -> Result<(i32, i32), String> {
+    let Some(monitor_id_to_find) = monitor_id_to_find else {
+        return Ok((0, 0));
+    };
+
+    let monitors = app
+        .available_monitors()
+        .map_err(|e| format!("Failed to enumerate monitors: {}", e))?;
+
+    monitors
+        .iter()
+        .find(|monitor| monitor_id(monitor) == *monitor_id_to_find)
+        .map(|monitor| (monitor.position().x, monitor.position().y))
+        .ok_or_else(|| format!("Unknown monitor id: {}", monitor_id_to_find))
 }
 
 // Launch all autostart widgets
@@ -520,20 +1299,16 @@ async fn launch_autostart_widgets(app: AppHandle) -> Result<(), String> {
 }
 
 // System monitoring commands
-#[tauri::command]
-async fn get_system_info(system: tauri::State<'_, Arc<Mutex<System>>>) -> Result<SystemInfo, String> {
-    let mut sys = system.lock().await;
-    
-    // Double refresh for accurate usage calculation
+//
+// Refreshes the shared System/Disks/Components handles in place and builds a
+// SystemInfo snapshot from them. Shared by the background monitor and the
+// one-shot fallback command so neither rebuilds Disks/Components from
+// scratch on every call.
+fn collect_system_info(sys: &mut System, disks: &mut Disks, components: &mut Components) -> SystemInfo {
     sys.refresh_all();
-    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-    sys.refresh_all();
-    
-    // Get CPU usage from global_cpu_info
-    let cpu_info = sys.global_cpu_info();
-    let cpu_usage = cpu_info.cpu_usage();
-    
-    // Get memory information
+
+    let cpu_usage = sys.global_cpu_info().cpu_usage();
+
     let total_memory = sys.total_memory();
     let used_memory = sys.used_memory();
     let memory_usage = if total_memory > 0 {
@@ -541,9 +1316,12 @@ async fn get_system_info(system: tauri::State<'_, Arc<Mutex<System>>>) -> Result
     } else {
         0.0
     };
-    
-    // Get disk information using separate Disks struct
-    let disks = Disks::new_with_refreshed_list();
+
+    if disks.list().is_empty() {
+        disks.refresh_list();
+    } else {
+        disks.refresh();
+    }
     let (disk_total, disk_used, disk_usage) = if let Some(disk) = disks.list().first() {
         let total = disk.total_space();
         let available = disk.available_space();
@@ -557,18 +1335,22 @@ async fn get_system_info(system: tauri::State<'_, Arc<Mutex<System>>>) -> Result
     } else {
         (0, 0, 0.0)
     };
-    
-    // Get CPU temperature using separate Components struct
-    let components = Components::new_with_refreshed_list();
-    let cpu_temp = components.list()
+
+    if components.list().is_empty() {
+        components.refresh_list();
+    } else {
+        components.refresh();
+    }
+    let cpu_temperature = components
+        .list()
         .iter()
         .find(|c| {
             let label = c.label().to_lowercase();
             label.contains("cpu") || label.contains("core") || label.contains("package")
         })
         .map(|c| c.temperature());
-    
-    Ok(SystemInfo {
+
+    SystemInfo {
         cpu_usage,
         memory_usage,
         memory_total: total_memory,
@@ -576,17 +1358,87 @@ async fn get_system_info(system: tauri::State<'_, Arc<Mutex<System>>>) -> Result
         disk_usage,
         disk_total,
         disk_used,
-        cpu_temperature: cpu_temp,
-    })
+        cpu_temperature,
+    }
+}
+
+// One-shot fallback for callers that don't want to subscribe to the
+// "system-info" event (see start_system_monitor).
+#[tauri::command]
+async fn get_system_info(state: tauri::State<'_, AppState>) -> Result<SystemInfo, String> {
+    let mut sys = state.system.lock().await;
+    let mut disks = state.disks.lock().await;
+    let mut components = state.components.lock().await;
+
+    Ok(collect_system_info(&mut sys, &mut disks, &mut components))
+}
+
+// Starts a background task that refreshes system metrics on `interval_ms`
+// and emits each snapshot as a "system-info" event, so widgets can subscribe
+// instead of polling get_system_info. Replaces any monitor already running.
+#[tauri::command]
+async fn start_system_monitor(interval_ms: u64, app: AppHandle) -> Result<(), String> {
+    stop_system_monitor(app.clone()).await?;
+
+    let state = app.state::<AppState>();
+    let system = state.system.clone();
+    let disks = state.disks.clone();
+    let components = state.components.clone();
+    let emitter = app.clone();
+
+    let task = tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(tokio::time::Duration::from_millis(interval_ms));
+        loop {
+            ticker.tick().await;
+
+            let mut sys = system.lock().await;
+            let mut disks = disks.lock().await;
+            let mut components = components.lock().await;
+            let info = collect_system_info(&mut sys, &mut disks, &mut components);
+
+            let _ = emitter.emit("system-info", info);
+        }
+    });
+
+    *state.monitor_task.lock().await = Some(task);
+    Ok(())
+}
+
+#[tauri::command]
+async fn stop_system_monitor(app: AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    if let Some(task) = state.monitor_task.lock().await.take() {
+        task.abort();
+    }
+    Ok(())
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .manage(AppState {
-            widget_windows: Mutex::new(HashMap::new()),
-            system: Arc::new(Mutex::new(System::new())),
+        .register_uri_scheme_protocol("widget", |app, request| handle_widget_protocol(app, request))
+        .manage({
+            let mut isolation_key = [0u8; 32];
+            rand::thread_rng().fill_bytes(&mut isolation_key);
+
+            AppState {
+                widget_windows: Mutex::new(HashMap::new()),
+                widget_documents: Mutex::new(HashMap::new()),
+                widget_dependency_scope: Mutex::new(HashMap::new()),
+                system: Arc::new(Mutex::new(System::new())),
+                disks: Arc::new(Mutex::new(Disks::new())),
+                components: Arc::new(Mutex::new(Components::new())),
+                monitor_task: Mutex::new(None),
+                isolation_key,
+            }
+        })
+        .setup(|app| {
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let _ = start_system_monitor(DEFAULT_SYSTEM_MONITOR_INTERVAL_MS, app_handle).await;
+            });
+            Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             get_widgets,
@@ -601,12 +1453,123 @@ pub fn run() {
             get_dependencies,
             add_dependency,
             remove_dependency,
+            fetch_dependency,
+            purge_cache,
             get_autostart,
             set_autostart,
-            get_screen_size,
+            get_monitors,
             launch_autostart_widgets,
-            get_system_info
+            get_system_info,
+            start_system_monitor,
+            stop_system_monitor,
+            isolation_encode,
+            handle_widget_message
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn monitor_fallback_id_encodes_geometry_not_enumeration_order() {
+        assert_eq!(monitor_fallback_id(1920, 1080, 0, 0), "1920x1080+0+0");
+        assert_eq!(monitor_fallback_id(2560, 1440, 1920, -200), "2560x1440+1920+-200");
+    }
+
+    #[test]
+    fn monitor_fallback_id_distinguishes_monitors_with_different_geometry() {
+        let a = monitor_fallback_id(1920, 1080, 0, 0);
+        let b = monitor_fallback_id(1920, 1080, 1920, 0);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn sha256_hex_matches_known_vectors() {
+        assert_eq!(
+            sha256_hex(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b85"
+        );
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn extension_for_dependency_prefers_the_url_extension() {
+        assert_eq!(extension_for_dependency("https://cdn.example.com/lib.js", None), "js");
+        assert_eq!(
+            extension_for_dependency("https://cdn.example.com/styles.CSS", Some("text/javascript")),
+            "css"
+        );
+    }
+
+    #[test]
+    fn extension_for_dependency_falls_back_to_content_type() {
+        assert_eq!(
+            extension_for_dependency("https://cdn.example.com/lib", Some("text/css; charset=utf-8")),
+            "css"
+        );
+        assert_eq!(
+            extension_for_dependency("https://cdn.example.com/lib", Some("application/javascript")),
+            "js"
+        );
+        assert_eq!(extension_for_dependency("https://cdn.example.com/lib", None), "bin");
+    }
+
+    #[test]
+    fn detect_package_format_from_prefers_appimage_over_flatpak_and_snap() {
+        assert_eq!(
+            detect_package_format_from(true, true, true),
+            PackageFormat::AppImage
+        );
+    }
+
+    #[test]
+    fn detect_package_format_from_prefers_flatpak_over_snap() {
+        assert_eq!(
+            detect_package_format_from(false, true, true),
+            PackageFormat::Flatpak
+        );
+    }
+
+    #[test]
+    fn detect_package_format_from_falls_back_to_native() {
+        assert_eq!(
+            detect_package_format_from(false, false, false),
+            PackageFormat::Native
+        );
+    }
+
+    // resolve_launch_command_for reads FLATPAK_ID/SNAP_NAME from the real
+    // process env, so tests touching them have to run one at a time or
+    // they'd stomp on each other across threads.
+    static ENV_TEST_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    #[test]
+    fn resolve_launch_command_for_flatpak_uses_the_app_id() {
+        let _guard = ENV_TEST_LOCK.lock().unwrap();
+        std::env::set_var("FLATPAK_ID", "io.dashlayer.App");
+        let result = resolve_launch_command_for(PackageFormat::Flatpak);
+        std::env::remove_var("FLATPAK_ID");
+        assert_eq!(result, Ok("flatpak run io.dashlayer.App".to_string()));
+    }
+
+    #[test]
+    fn resolve_launch_command_for_flatpak_errors_without_an_app_id() {
+        let _guard = ENV_TEST_LOCK.lock().unwrap();
+        std::env::remove_var("FLATPAK_ID");
+        assert!(resolve_launch_command_for(PackageFormat::Flatpak).is_err());
+    }
+
+    #[test]
+    fn resolve_launch_command_for_snap_defaults_the_snap_name() {
+        let _guard = ENV_TEST_LOCK.lock().unwrap();
+        std::env::remove_var("SNAP_NAME");
+        let result = resolve_launch_command_for(PackageFormat::Snap);
+        assert_eq!(result, Ok("snap run dashlayer".to_string()));
+    }
+}